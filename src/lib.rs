@@ -0,0 +1,11 @@
+//! The parsing/generation core of `tabol`, split out from `main.rs` so it
+//! can be used as a library dependency and not only through the CLI/REPL
+//! binary. `main.rs` depends on this crate the same way an external
+//! embedder would.
+
+pub mod nom_parser;
+pub mod owned;
+pub mod tabol;
+
+pub use crate::owned::OwnedTabol;
+pub use crate::tabol::{register_filter, FilterHandler, FilterOp, Tabol, TableDefinition, TableError};
@@ -1,13 +1,9 @@
-#![feature(lazy_cell)]
-
-mod nom_parser;
-mod tabol;
-
 use clap::Parser;
-use std::sync::LazyLock;
-use std::{error::Error, fs};
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
 
-use crate::tabol::TableError;
+use tabol::{Tabol, TableError};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -23,18 +19,24 @@ struct Args {
 
     #[arg(long, default_value_t = false)]
     debug: bool,
+
+    /// Drop into an interactive shell instead of generating once and exiting.
+    #[arg(short, long, default_value_t = false)]
+    interactive: bool,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    static TABLE_DEF: LazyLock<String> = LazyLock::new(|| {
-        let args = Args::parse();
-        let file_path = format!("./src/tables/{}.tbl", args.definition);
-        println!("filepath: {}", file_path);
-        fs::read_to_string(file_path).expect("Should have been able to read the file")
-    });
-
     let args = Args::parse();
-    let tabol = tabol::Tabol::new(TABLE_DEF.trim());
+    let file_path = PathBuf::from(format!("./src/tables/{}.tbl", args.definition));
+    println!("filepath: {}", file_path.display());
+
+    let sources = Tabol::resolve_import_graph(&file_path)?;
+
+    if args.interactive {
+        return repl(sources);
+    }
+
+    let tabol = build_tabol(&sources);
     let table_name = args.table.unwrap_or(args.definition);
 
     if let Ok(tabol) = tabol {
@@ -67,3 +69,107 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+/// Leaks each source (so it satisfies `Tabol`'s `&'static str` table map) and
+/// merges them with `Tabol::from_sources`.
+fn build_tabol(sources: &[String]) -> Result<Tabol<'static>, TableError> {
+    let leaked: Vec<&'static str> = sources
+        .iter()
+        .map(|source| -> &'static str { Box::leak(source.clone().into_boxed_str()) })
+        .collect();
+
+    Tabol::from_sources(leaked)
+}
+
+/// An exploration shell for iterating on generators: loads `initial_sources`
+/// once, then repeatedly reads either a `<table> [count]` command (generated
+/// against the table map already in memory) or a pasted/typed table
+/// definition, which is added as a new source and used to hot-reload the
+/// session.
+///
+/// Parse and call errors are printed with `TableError`'s contextual
+/// source-line rendering rather than aborting the shell.
+fn repl(initial_sources: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut sources = initial_sources;
+    let mut tabol = match build_tabol(&sources) {
+        Ok(tabol) => tabol,
+        Err(err) => return Err(err.into()),
+    };
+
+    println!("tabol interactive shell");
+    println!("  `<table> [count]`        generate from a loaded table");
+    println!("  paste a table definition, then a blank line, to hot-reload it");
+
+    let stdin = io::stdin();
+    let mut pending_definition = String::new();
+
+    loop {
+        print!("{}> ", if pending_definition.is_empty() { "" } else { "... " });
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end_matches('\n');
+
+        if pending_definition.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+
+        // A blank line terminates a pasted/typed definition and triggers the reload.
+        if !pending_definition.is_empty() && line.trim().is_empty() {
+            sources.push(pending_definition.clone());
+            pending_definition.clear();
+
+            match build_tabol(&sources) {
+                Ok(reloaded) => {
+                    tabol = reloaded;
+                    println!("[reloaded table definitions]");
+                }
+                Err(err) => {
+                    sources.pop();
+                    eprintln!("{}", err);
+                }
+            }
+
+            continue;
+        }
+
+        // Anything starting a `---` frontmatter block (or already mid-definition)
+        // is accumulated rather than treated as a generation command.
+        if !pending_definition.is_empty() || line.trim_start().starts_with("---") {
+            pending_definition.push_str(line);
+            pending_definition.push('\n');
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let Some(table_id) = tokens.next() else {
+            continue;
+        };
+        let count: u8 = tokens.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+
+        if !tabol.contains_table(table_id) {
+            eprintln!(
+                "{}",
+                TableError::CallError(format!(
+                    "Table definition does not have a table with id \"{}\"",
+                    table_id
+                ))
+            );
+            continue;
+        }
+
+        match tabol.gen_many(table_id, count) {
+            Ok(results) => {
+                for result in results {
+                    println!("{}\n", result);
+                }
+            }
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use nom::{
     bytes::complete::{take_until, take_while1},
     character::complete::{alphanumeric1, digit1, line_ending, multispace0, not_line_ending},
-    combinator::map_parser,
+    combinator::{map_parser, verify},
     error::make_error,
     multi::{fold_many1, many0, many1, separated_list1},
     number::complete::float,
@@ -12,11 +12,41 @@ use nom_supreme::{error::ErrorTree, tag::complete::tag};
 use nom_supreme::{final_parser::final_parser, parser_ext::ParserExt};
 use std::collections::HashMap;
 
-use crate::tabol::{FilterOp, Rule, RuleInst, TableDefinition};
+use crate::tabol::{is_registered_filter, FilterOp, Rule, RuleInst, TableDefinition, TableError};
 
 // --------- Tabol ---------
-pub fn parse_tables(input: &str) -> Result<Vec<TableDefinition>, ErrorTree<&str>> {
-    final_parser(many1(table))(input)
+
+/// `parse_tables` fails for two unrelated reasons, which callers need to
+/// tell apart: a syntax error from nom itself (rendered against the source
+/// text), or a semantically invalid table definition caught once a
+/// `TableDefinition` is actually constructed (e.g. a table whose rules all
+/// have weight `0.0`). Kept distinct here rather than eagerly converting
+/// to `TableError`, since only the caller knows whether its source is
+/// `'static` (and can build a `TableError::ParseError`) or not (and must
+/// render the tree into a `TableError::InvalidDefinition` instead).
+pub enum TablesError<'a> {
+    Syntax(ErrorTree<&'a str>),
+    InvalidDefinition(TableError),
+}
+
+// Returns the parsed tables alongside every `import:` path named in any of
+// their frontmatter blocks, so the caller can load and merge the rest of
+// a multi-file table library before validating.
+pub fn parse_tables(input: &str) -> Result<(Vec<TableDefinition<'_>>, Vec<&str>), TablesError<'_>> {
+    let entries = final_parser(many1(table))(input).map_err(TablesError::Syntax)?;
+
+    let mut tables = Vec::with_capacity(entries.len());
+    let mut imports = Vec::new();
+
+    for (frontmatter, rules) in entries {
+        let table = TableDefinition::new(frontmatter.title, frontmatter.id, rules)
+            .map_err(TablesError::InvalidDefinition)?;
+
+        tables.push(table);
+        imports.extend(frontmatter.imports);
+    }
+
+    Ok((tables, imports))
 }
 
 /**
@@ -31,24 +61,24 @@ pub fn parse_tables(input: &str) -> Result<Vec<TableDefinition>, ErrorTree<&str>
  *   └───────────────────┘
  *
  */
-fn table(input: &str) -> IResult<&str, TableDefinition<'_>, ErrorTree<&str>> {
+fn table(input: &str) -> IResult<&str, (Frontmatter<'_>, Vec<Rule<'_>>), ErrorTree<&str>> {
     tuple((frontmatter, rules))
         .context("Invalid table definition")
-        .map(|(frontmatter, rules)| TableDefinition::new(frontmatter.title, frontmatter.id, rules))
         .parse(input)
 }
 
 struct Frontmatter<'a> {
     pub title: &'a str,
     pub id: &'a str,
+    pub imports: Vec<&'a str>,
 }
 
-fn frontmatter(input: &str) -> IResult<&str, Frontmatter, ErrorTree<&str>> {
+fn frontmatter(input: &str) -> IResult<&str, Frontmatter<'_>, ErrorTree<&str>> {
     let (input, attrs) = fold_many1(
         frontmatter_attr,
         HashMap::new,
-        |mut acc: HashMap<_, _>, (k, v)| {
-            acc.insert(k, v);
+        |mut acc: HashMap<_, Vec<_>>, (k, v)| {
+            acc.entry(k).or_default().push(v);
             acc
         },
     )
@@ -59,17 +89,35 @@ fn frontmatter(input: &str) -> IResult<&str, Frontmatter, ErrorTree<&str>> {
     .parse(input)?;
 
     // arbitary frontmatter???
-    let id = attrs.get("id").ok_or(nom::Err::Failure(make_error(
-        input,
-        nom::error::ErrorKind::Many1,
-    )))?;
+    let id = attrs
+        .get("id")
+        .and_then(|values| values.first())
+        .copied()
+        .ok_or(nom::Err::Failure(make_error(
+            input,
+            nom::error::ErrorKind::Many1,
+        )))?;
 
-    let title = attrs.get("title").ok_or(nom::Err::Failure(make_error(
-        input,
-        nom::error::ErrorKind::Many1,
-    )))?;
+    let title = attrs
+        .get("title")
+        .and_then(|values| values.first())
+        .copied()
+        .ok_or(nom::Err::Failure(make_error(
+            input,
+            nom::error::ErrorKind::Many1,
+        )))?;
+
+    // repeatable, so a table can pull in several other definition files
+    let imports = attrs.get("import").cloned().unwrap_or_default();
 
-    Ok((input, Frontmatter { id, title }))
+    Ok((
+        input,
+        Frontmatter {
+            id,
+            title,
+            imports,
+        },
+    ))
 }
 
 fn frontmatter_attr(input: &str) -> IResult<&str, (&str, &str), ErrorTree<&str>> {
@@ -84,13 +132,13 @@ fn frontmatter_attr(input: &str) -> IResult<&str, (&str, &str), ErrorTree<&str>>
 }
 
 // --------- Rules ---------
-fn rules(input: &str) -> IResult<&str, Vec<Rule>, ErrorTree<&str>> {
+fn rules(input: &str) -> IResult<&str, Vec<Rule<'_>>, ErrorTree<&str>> {
     separated_list1(line_ending, rule_line)
         .terminated(multispace0)
         .parse(input)
 }
 
-fn rule_line(input: &str) -> IResult<&str, Rule, ErrorTree<&str>> {
+fn rule_line(input: &str) -> IResult<&str, Rule<'_>, ErrorTree<&str>> {
     // the `map_parser(not_line_ending, rule_line)` is important, so that
     // `rule_line` doesn't parse past '\n' at the end of the current line
     map_parser(
@@ -107,14 +155,14 @@ fn rule_line(input: &str) -> IResult<&str, Rule, ErrorTree<&str>> {
 }
 
 // --------- Rule ---------
-pub fn rule(input: &str) -> IResult<&str, (&str, Vec<RuleInst>), ErrorTree<&str>> {
+pub fn rule(input: &str) -> IResult<&str, (&str, Vec<RuleInst<'_>>), ErrorTree<&str>> {
     many1(rule_dice_roll.or(rule_interpolation).or(rule_literal))
         .context("Invalid rule text, expected a dice roll (`2d4`), an interpolation (`{{other}}`) or a literal")
         .with_recognized()
         .parse(input)
 }
 
-fn rule_dice_roll(input: &str) -> IResult<&str, RuleInst, ErrorTree<&str>> {
+fn rule_dice_roll(input: &str) -> IResult<&str, RuleInst<'_>, ErrorTree<&str>> {
     tuple((
         digit1.parse_from_str(),
         digit1.parse_from_str().preceded_by(tag("d")),
@@ -129,7 +177,7 @@ fn rule_dice_roll(input: &str) -> IResult<&str, RuleInst, ErrorTree<&str>> {
     .parse(input)
 }
 
-fn rule_literal(input: &str) -> IResult<&str, RuleInst, ErrorTree<&str>> {
+fn rule_literal(input: &str) -> IResult<&str, RuleInst<'_>, ErrorTree<&str>> {
     // can't just do `take_until("{{").or(not_line_ending)` or else we'll
     // successfully parse "" which causes many1 to fail
     map_parser(take_until("{{").or(not_line_ending), literal)
@@ -138,35 +186,66 @@ fn rule_literal(input: &str) -> IResult<&str, RuleInst, ErrorTree<&str>> {
         .parse(input)
 }
 
-fn rule_interpolation(input: &str) -> IResult<&str, RuleInst, ErrorTree<&str>> {
+fn rule_interpolation(input: &str) -> IResult<&str, RuleInst<'_>, ErrorTree<&str>> {
     pipeline
         .preceded_by(tag("{{"))
         .terminated(tag("}}"))
         .context("rule interpolation")
-        .map(|(s, filters)| RuleInst::Interpolation(s, filters))
         .parse(input)
 }
 
-fn pipeline(input: &str) -> IResult<&str, (&str, Vec<FilterOp>), ErrorTree<&str>> {
-    pair(ident.cut(), filters)
+fn pipeline(input: &str) -> IResult<&str, RuleInst<'_>, ErrorTree<&str>> {
+    reference
+        .or(assignment)
+        .or(interpolation)
         .context("interpolation pipeline")
         .parse(input)
 }
 
-fn filters(input: &str) -> IResult<&str, Vec<FilterOp>, ErrorTree<&str>> {
-    many0(ident.preceded_by(tag("|")))
-        .map(|filters| {
-            filters
-                .iter()
-                .map(|&filter| match filter {
-                    "definite" => FilterOp::DefiniteArticle,
-                    "indefinite" => FilterOp::IndefiniteArticle,
-                    "capitalize" => FilterOp::Capitalize,
-                    // better way to return error from `map` parser?
-                    _ => panic!("unknown filter: {}", filter),
-                })
-                .collect()
-        })
+// `$name|filters` — reference a value bound earlier in the rule by an `assignment`
+fn reference(input: &str) -> IResult<&str, RuleInst<'_>, ErrorTree<&str>> {
+    pair(ident.cut().preceded_by(tag("$")), filters)
+        .context("variable reference")
+        .map(|(name, filters)| RuleInst::Reference(name, filters))
+        .parse(input)
+}
+
+// `name = table|filters` — generate from `table` and bind the result to `name`
+fn assignment(input: &str) -> IResult<&str, RuleInst<'_>, ErrorTree<&str>> {
+    tuple((ident, ident.cut().preceded_by(tag(" = ")), filters))
+        .context("variable assignment")
+        .map(|(name, table_id, filters)| RuleInst::Assignment(name, table_id, filters))
+        .parse(input)
+}
+
+// `table|filters` — generate from `table` directly
+fn interpolation(input: &str) -> IResult<&str, RuleInst<'_>, ErrorTree<&str>> {
+    pair(ident.cut(), filters)
+        .context("table interpolation")
+        .map(|(id, filters)| RuleInst::Interpolation(id, filters))
+        .parse(input)
+}
+
+fn filters(input: &str) -> IResult<&str, Vec<FilterOp<'_>>, ErrorTree<&str>> {
+    many0(filter.preceded_by(tag("|"))).parse(input)
+}
+
+// a filter is `name` optionally followed by one or more `:`-separated
+// arguments, e.g. `upper`, `repeat:3`, `replace:old:new`
+fn filter(input: &str) -> IResult<&str, FilterOp<'_>, ErrorTree<&str>> {
+    verify(
+        pair(ident, many0(filter_arg.preceded_by(tag(":")))),
+        |(name, _): &(&str, Vec<&str>)| is_registered_filter(name),
+    )
+    .context("Unknown filter")
+    .cut()
+    .map(|(name, args)| FilterOp { name, args })
+    .parse(input)
+}
+
+fn filter_arg(input: &str) -> IResult<&str, &str, ErrorTree<&str>> {
+    take_while1(|c: char| c != ':' && c != '|' && c != '}')
+        .context("filter argument")
         .parse(input)
 }
 
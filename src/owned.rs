@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+
+use crate::nom_parser;
+use crate::tabol::{FilterOp, Rule, RuleInst, Tabol, TableDefinition, TableError};
+
+/// Owned counterpart to the borrowed `tabol` model: every string is an owned
+/// `String` rather than a slice borrowed from `.tbl` source text, so table
+/// definitions fetched at runtime (a database row, an HTTP response, a
+/// hand-written JSON/TOML file, ...) don't have to be leaked to satisfy
+/// `Tabol`'s `&'static str` bound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedTabol {
+    pub tables: Vec<OwnedTableDefinition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedTableDefinition {
+    pub title: String,
+    pub id: String,
+    pub rules: Vec<OwnedRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedRule {
+    pub raw: String,
+    pub weight: f32,
+    pub parts: Vec<OwnedRuleInst>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OwnedRuleInst {
+    DiceRoll {
+        count: usize,
+        sides: usize,
+    },
+    Literal {
+        value: String,
+    },
+    Interpolation {
+        table: String,
+        filters: Vec<OwnedFilterOp>,
+    },
+    Assignment {
+        name: String,
+        table: String,
+        filters: Vec<OwnedFilterOp>,
+    },
+    Reference {
+        name: String,
+        filters: Vec<OwnedFilterOp>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedFilterOp {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl OwnedTabol {
+    /// Parses `.tbl` source text the same way `Tabol::new` does, but copies
+    /// every borrowed slice into an owned `String` so the result doesn't
+    /// borrow from (or need to outlive) `source`.
+    pub fn parse(source: &str) -> Result<Self, TableError> {
+        let (tables, _imports) = nom_parser::parse_tables(source).map_err(|e| match e {
+            nom_parser::TablesError::Syntax(e) => {
+                TableError::InvalidDefinition(crate::tabol::render_parse_error(source, &e))
+            }
+            nom_parser::TablesError::InvalidDefinition(e) => e,
+        })?;
+
+        Ok(Self {
+            tables: tables.iter().map(OwnedTableDefinition::from_borrowed).collect(),
+        })
+    }
+
+    /// Loads the owned model from its JSON representation: `title`, `id`,
+    /// and weighted rules as structured data, rather than the `.tbl` DSL.
+    pub fn from_json(json: &str) -> Result<Self, TableError> {
+        serde_json::from_str(json).map_err(|e| {
+            TableError::InvalidDefinition(format!("invalid JSON table definition: {}", e))
+        })
+    }
+
+    /// Loads the owned model from its TOML representation.
+    pub fn from_toml(toml: &str) -> Result<Self, TableError> {
+        toml::from_str(toml).map_err(|e| {
+            TableError::InvalidDefinition(format!("invalid TOML table definition: {}", e))
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, TableError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            TableError::InvalidDefinition(format!("failed to serialize table definitions: {}", e))
+        })
+    }
+
+    pub fn to_toml(&self) -> Result<String, TableError> {
+        toml::to_string_pretty(self).map_err(|e| {
+            TableError::InvalidDefinition(format!("failed to serialize table definitions: {}", e))
+        })
+    }
+
+    /// Builds a borrowed `Tabol` that reads its table and rule text out of
+    /// `self`'s owned strings, so a session can run off the owned model
+    /// directly instead of leaking a `&'static str`.
+    pub fn to_tabol(&self) -> Result<Tabol<'_>, TableError> {
+        let tables = self
+            .tables
+            .iter()
+            .map(OwnedTableDefinition::to_borrowed)
+            .collect::<Result<Vec<_>, TableError>>()?;
+
+        Tabol::from_table_definitions(tables)
+    }
+}
+
+impl OwnedTableDefinition {
+    fn from_borrowed(table: &TableDefinition) -> Self {
+        Self {
+            title: table.title.to_string(),
+            id: table.id.to_string(),
+            rules: table.rules.iter().map(OwnedRule::from_borrowed).collect(),
+        }
+    }
+
+    fn to_borrowed(&self) -> Result<TableDefinition<'_>, TableError> {
+        if self.rules.is_empty() {
+            return Err(TableError::InvalidDefinition(format!(
+                "table \"{}\" has no rules",
+                self.id
+            )));
+        }
+
+        TableDefinition::new(
+            &self.title,
+            &self.id,
+            self.rules.iter().map(OwnedRule::to_borrowed).collect(),
+        )
+    }
+}
+
+impl OwnedRule {
+    fn from_borrowed(rule: &Rule) -> Self {
+        Self {
+            raw: rule.raw.to_string(),
+            weight: rule.weight,
+            parts: rule.parts.iter().map(OwnedRuleInst::from_borrowed).collect(),
+        }
+    }
+
+    fn to_borrowed(&self) -> Rule<'_> {
+        Rule {
+            raw: &self.raw,
+            weight: self.weight,
+            parts: self.parts.iter().map(OwnedRuleInst::to_borrowed).collect(),
+        }
+    }
+}
+
+impl OwnedRuleInst {
+    fn from_borrowed(part: &RuleInst) -> Self {
+        match part {
+            RuleInst::DiceRoll(count, sides) => Self::DiceRoll {
+                count: *count,
+                sides: *sides,
+            },
+            RuleInst::Literal(value) => Self::Literal {
+                value: value.to_string(),
+            },
+            RuleInst::Interpolation(table, filters) => Self::Interpolation {
+                table: table.to_string(),
+                filters: filters.iter().map(OwnedFilterOp::from_borrowed).collect(),
+            },
+            RuleInst::Assignment(name, table, filters) => Self::Assignment {
+                name: name.to_string(),
+                table: table.to_string(),
+                filters: filters.iter().map(OwnedFilterOp::from_borrowed).collect(),
+            },
+            RuleInst::Reference(name, filters) => Self::Reference {
+                name: name.to_string(),
+                filters: filters.iter().map(OwnedFilterOp::from_borrowed).collect(),
+            },
+        }
+    }
+
+    fn to_borrowed(&self) -> RuleInst<'_> {
+        match self {
+            Self::DiceRoll { count, sides } => RuleInst::DiceRoll(*count, *sides),
+            Self::Literal { value } => RuleInst::Literal(value),
+            Self::Interpolation { table, filters } => {
+                RuleInst::Interpolation(table, filters.iter().map(OwnedFilterOp::to_borrowed).collect())
+            }
+            Self::Assignment {
+                name,
+                table,
+                filters,
+            } => RuleInst::Assignment(
+                name,
+                table,
+                filters.iter().map(OwnedFilterOp::to_borrowed).collect(),
+            ),
+            Self::Reference { name, filters } => {
+                RuleInst::Reference(name, filters.iter().map(OwnedFilterOp::to_borrowed).collect())
+            }
+        }
+    }
+}
+
+impl OwnedFilterOp {
+    fn from_borrowed(filter: &FilterOp) -> Self {
+        Self {
+            name: filter.name.to_string(),
+            args: filter.args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    fn to_borrowed(&self) -> FilterOp<'_> {
+        FilterOp {
+            name: &self.name,
+            args: self.args.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_owned_tabol() -> OwnedTabol {
+        OwnedTabol {
+            tables: vec![OwnedTableDefinition {
+                title: "NPC Name".to_string(),
+                id: "npc_name".to_string(),
+                rules: vec![OwnedRule {
+                    raw: "Aragorn".to_string(),
+                    weight: 1.0,
+                    parts: vec![OwnedRuleInst::Literal {
+                        value: "Aragorn".to_string(),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = sample_owned_tabol().to_json().unwrap();
+        let reparsed = OwnedTabol::from_json(&json).unwrap();
+
+        assert_eq!(reparsed.tables[0].id, "npc_name");
+        assert_eq!(reparsed.to_tabol().unwrap().gen("npc_name").unwrap(), "Aragorn");
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let toml = sample_owned_tabol().to_toml().unwrap();
+        let reparsed = OwnedTabol::from_toml(&toml).unwrap();
+
+        assert_eq!(reparsed.tables[0].id, "npc_name");
+        assert_eq!(reparsed.to_tabol().unwrap().gen("npc_name").unwrap(), "Aragorn");
+    }
+
+    #[test]
+    fn test_parse_matches_tbl_dsl() {
+        let source = "\
+---
+id: npc_name
+title: NPC Name
+---
+1: Aragorn
+";
+
+        let owned = OwnedTabol::parse(source).unwrap();
+
+        assert_eq!(owned.tables.len(), 1);
+        assert_eq!(owned.tables[0].id, "npc_name");
+        assert_eq!(owned.to_tabol().unwrap().gen("npc_name").unwrap(), "Aragorn");
+    }
+
+    #[test]
+    fn test_empty_rules_is_an_error_not_a_panic() {
+        let owned = OwnedTabol {
+            tables: vec![OwnedTableDefinition {
+                title: "Empty".to_string(),
+                id: "empty".to_string(),
+                rules: vec![],
+            }],
+        };
+
+        assert!(matches!(
+            owned.to_tabol(),
+            Err(TableError::InvalidDefinition(_))
+        ));
+    }
+
+    #[test]
+    fn test_zero_weight_table_is_an_error_not_a_panic() {
+        let owned = OwnedTabol {
+            tables: vec![OwnedTableDefinition {
+                title: "Zero".to_string(),
+                id: "zero".to_string(),
+                rules: vec![OwnedRule {
+                    raw: "foo".to_string(),
+                    weight: 0.0,
+                    parts: vec![OwnedRuleInst::Literal {
+                        value: "foo".to_string(),
+                    }],
+                }],
+            }],
+        };
+
+        assert!(matches!(
+            owned.to_tabol(),
+            Err(TableError::InvalidDefinition(_))
+        ));
+    }
+}
@@ -1,11 +1,19 @@
+use log::{info, warn};
 use nom_supreme::error::GenericErrorTree;
 use nom_supreme::final_parser::{Location, RecreateContext};
-use rand::distributions::{Uniform, WeightedIndex};
+use rand::distributions::{Uniform, WeightedError, WeightedIndex};
 use rand::prelude::*;
 use std::error::Error;
-use std::{collections::HashMap, fmt};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt, fs,
+};
 
 use crate::nom_parser;
+use crate::owned::OwnedTabol;
 
 type TableId<'a> = &'a str;
 
@@ -24,109 +32,414 @@ impl Error for TableError {}
 impl fmt::Display for TableError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            TableError::ParseError(source, e) => {
-                writeln!(f, "Table syntax is invalid")?;
-                writeln!(f, "-----------------------------")?;
-
-                match e {
-                    GenericErrorTree::Base { location, kind } => {
-                        // XXX: why do we get only Base sometimes, and why does it contain no information about the problem? "Expected eof"
-                        write_base_error(f, source, location, format!("{}", kind).as_ref())?;
-                    }
-                    GenericErrorTree::Stack { base: _, contexts } => {
-                        // XXX: just grab the "most recent" error right now
-                        for context in contexts.iter().take(1) {
-                            write_base_error(f, source, context.0, context.1.to_string().as_ref())?;
-                            writeln!(f, "-----------------------------")?;
-                        }
-                    }
-                    _ => (),
-                }
-            }
+            TableError::ParseError(source, e) => write!(f, "{}", render_parse_error(source, e)),
             TableError::InvalidDefinition(msg) => {
-                write!(f, "invalid table definition: {}", msg)?;
+                write!(f, "invalid table definition: {}", msg)
             }
             TableError::CallError(msg) => {
-                write!(f, "invalid table call: {}", msg)?;
+                write!(f, "invalid table call: {}", msg)
             }
         }
-
-        Ok(())
     }
 }
 
-fn write_base_error(
-    f: &mut fmt::Formatter,
+/// Renders a nom-supreme error tree the same way `TableError::ParseError`'s
+/// `Display` does. Factored out so callers that parse a non-`'static`
+/// source (e.g. `OwnedTabol::parse`) can render the tree into an owned
+/// `String` before it goes out of scope, instead of needing to keep the
+/// borrowed tree around inside a `TableError::ParseError`.
+pub(crate) fn render_parse_error(
     source: &str,
-    location: &str,
-    msg: &str,
-) -> fmt::Result {
+    e: &GenericErrorTree<&str, &str, &str, Box<dyn Error + Send + Sync>>,
+) -> String {
+    let mut rendered = String::new();
+    let _ = writeln!(rendered, "Table syntax is invalid");
+    let _ = writeln!(rendered, "-----------------------------");
+
+    match e {
+        GenericErrorTree::Base { location, kind } => {
+            // XXX: why do we get only Base sometimes, and why does it contain no information about the problem? "Expected eof"
+            let _ = write_base_error(&mut rendered, source, location, format!("{}", kind).as_ref());
+        }
+        GenericErrorTree::Stack { base: _, contexts } => {
+            // XXX: just grab the "most recent" error right now
+            for context in contexts.iter().take(1) {
+                let _ = write_base_error(&mut rendered, source, context.0, context.1.to_string().as_ref());
+                let _ = writeln!(rendered, "-----------------------------");
+            }
+        }
+        _ => (),
+    }
+
+    rendered
+}
+
+fn write_base_error(w: &mut impl fmt::Write, source: &str, location: &str, msg: &str) -> fmt::Result {
     let Location { line, column } = Location::recreate_context(source, location);
 
     for (i, l) in contextual_lines(source, line, 3) {
-        writeln!(f, "{}", l)?;
+        writeln!(w, "{}", l)?;
 
         // line is 1-indexed, i is 0-indexed
         let indent = column - 1;
         if i == line - 1 {
-            writeln!(f, "{:indent$}^-- {}", "", msg)?;
+            writeln!(w, "{:indent$}^-- {}", "", msg)?;
         }
     }
 
     Ok(())
 }
 
+/// Renders the same caret-underlined context `write_base_error` gives parse
+/// errors, but for a location discovered during static validation rather
+/// than from a nom error tree.
+fn describe_location(source: &str, location: &str, msg: &str) -> String {
+    let mut rendered = String::new();
+    let _ = write_base_error(&mut rendered, source, location, msg);
+    rendered
+}
+
 fn contextual_lines(
     text: &str,
     line: usize,
     n_lines: usize,
 ) -> impl Iterator<Item = (usize, &str)> {
-    let start = line - n_lines;
+    // `line` can be as small as 1, so a plain subtraction underflows for an
+    // error on one of the first `n_lines` lines of the source.
+    let start = line.saturating_sub(n_lines);
     let end = line + n_lines;
-    let skip = start.max(0);
 
-    text.lines().enumerate().skip(skip).take(end - start)
+    text.lines().enumerate().skip(start).take(end - start)
 }
 
 #[derive(Debug)]
 pub struct Tabol<'a> {
     table_map: HashMap<&'a str, TableDefinition<'a>>,
+    sources: Vec<&'a str>,
 }
 
 impl<'a> Tabol<'a> {
     pub fn new(table_definitions: &'static str) -> Result<Self, TableError> {
+        Self::from_sources(vec![table_definitions])
+    }
+
+    /// Reads `entry_path` and every file it names via a repeatable `import:`
+    /// frontmatter attribute, resolving each import relative to the file
+    /// that named it, then merges the whole set into a single table map —
+    /// the real entry point for multi-file table libraries. Imports are
+    /// discovered from the parser's own `Frontmatter.imports` output (the
+    /// same source of truth parsing itself uses), rather than a
+    /// caller-side scan of the raw file text, so a caller going through
+    /// `Tabol::new`/`from_sources` directly can't end up with a different
+    /// idea of what counts as an import.
+    ///
+    /// Unlike `resolve_import_graph` (which `main.rs`'s REPL uses to get
+    /// back at the raw source text for hot-reloading), this walks the
+    /// graph and parses each file exactly once, building the table map
+    /// straight from the already-parsed tables instead of handing the
+    /// source text to `from_sources` for a second parse.
+    pub fn from_files(entry_path: &Path) -> Result<Tabol<'static>, TableError> {
+        let (sources, tables) = Self::resolve_and_parse_import_graph(entry_path)?;
+
         let mut table_map = HashMap::new();
-        let tables = nom_parser::parse_tables(table_definitions)
-            .map_err(|e| TableError::ParseError(table_definitions.to_string(), e))?;
 
         for table in tables {
+            if table_map.contains_key(table.id) {
+                return Err(TableError::InvalidDefinition(format!(
+                    "table id \"{}\" is defined more than once across the loaded files",
+                    table.id
+                )));
+            }
+
             table_map.insert(table.id, table);
         }
 
-        let tabol = Self { table_map };
+        // `sources` is in place before validation, so an undefined table id
+        // is rendered against the file it appears in
+        Tabol { table_map, sources }.validate_tables()
+    }
+
+    /// Does the same traversal as `resolve_import_graph`, but leaks each
+    /// source to `'static` and parses it immediately, returning both the
+    /// source texts (for `source_containing`'s error rendering) and the
+    /// parsed tables — so `from_files` never parses a file twice.
+    fn resolve_and_parse_import_graph(
+        entry_path: &Path,
+    ) -> Result<(Vec<&'static str>, Vec<TableDefinition<'static>>), TableError> {
+        let mut sources = Vec::new();
+        let mut tables = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([entry_path.to_path_buf()]);
+
+        while let Some(path) = queue.pop_front() {
+            let canonical: PathBuf = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            if !seen.insert(canonical) {
+                continue;
+            }
+
+            let source = fs::read_to_string(&path)
+                .map_err(|e| {
+                    TableError::InvalidDefinition(format!(
+                        "failed to read \"{}\": {}",
+                        path.display(),
+                        e
+                    ))
+                })?
+                .trim()
+                .to_string();
+            let source: &'static str = Box::leak(source.into_boxed_str());
+
+            let (parsed_tables, imports) = nom_parser::parse_tables(source).map_err(|e| match e {
+                nom_parser::TablesError::Syntax(e) => {
+                    TableError::InvalidDefinition(render_parse_error(source, &e))
+                }
+                nom_parser::TablesError::InvalidDefinition(e) => e,
+            })?;
+
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            for name in imports {
+                queue.push_back(dir.join(format!("{}.tbl", name.trim())));
+            }
+
+            tables.extend(parsed_tables);
+            sources.push(source);
+        }
+
+        Ok((sources, tables))
+    }
+
+    /// Walks the import graph starting at `entry_path`, returning every
+    /// source file's contents in load order. Already-visited files (by
+    /// canonical path) are skipped, so import cycles terminate harmlessly.
+    /// Exposed so callers that need the individual source strings (e.g.
+    /// `main.rs`'s REPL, which keeps appending to and re-parsing the list)
+    /// don't have to reimplement import discovery themselves.
+    pub fn resolve_import_graph(entry_path: &Path) -> Result<Vec<String>, TableError> {
+        let mut sources = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([entry_path.to_path_buf()]);
+
+        while let Some(path) = queue.pop_front() {
+            let canonical: PathBuf = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            if !seen.insert(canonical) {
+                continue;
+            }
+
+            let source = fs::read_to_string(&path)
+                .map_err(|e| {
+                    TableError::InvalidDefinition(format!(
+                        "failed to read \"{}\": {}",
+                        path.display(),
+                        e
+                    ))
+                })?
+                .trim()
+                .to_string();
+
+            let (_, imports) = nom_parser::parse_tables(&source).map_err(|e| match e {
+                nom_parser::TablesError::Syntax(e) => {
+                    TableError::InvalidDefinition(render_parse_error(&source, &e))
+                }
+                nom_parser::TablesError::InvalidDefinition(e) => e,
+            })?;
+
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            for name in imports {
+                queue.push_back(dir.join(format!("{}.tbl", name.trim())));
+            }
+
+            sources.push(source);
+        }
+
+        Ok(sources)
+    }
+
+    /// Parses and merges several `.tbl` sources into a single table map, so a
+    /// generation session can span files split apart via `import:`
+    /// frontmatter. Table ids must be unique across the whole set; callers
+    /// are expected to have already collected the full, import-resolved set
+    /// of sources before calling this (see `from_files`).
+    pub fn from_sources(sources: Vec<&'static str>) -> Result<Self, TableError> {
+        let mut table_map = HashMap::new();
+
+        for &table_definitions in sources.iter() {
+            let (tables, _imports) = nom_parser::parse_tables(table_definitions).map_err(|e| match e {
+                nom_parser::TablesError::Syntax(e) => {
+                    TableError::ParseError(table_definitions.to_string(), e)
+                }
+                nom_parser::TablesError::InvalidDefinition(e) => e,
+            })?;
+
+            for table in tables {
+                if table_map.contains_key(table.id) {
+                    return Err(TableError::InvalidDefinition(format!(
+                        "table id \"{}\" is defined more than once across the loaded files",
+                        table.id
+                    )));
+                }
+
+                table_map.insert(table.id, table);
+            }
+        }
+
+        let tabol = Self { table_map, sources };
 
         tabol.validate_tables()
     }
 
+    /// Builds a `Tabol` directly from already-parsed table definitions,
+    /// rather than `.tbl` source text — the path `OwnedTabol::to_tabol` uses
+    /// to run a session off owned (e.g. JSON/TOML-loaded) table data without
+    /// leaking a `&'static str`.
+    pub(crate) fn from_table_definitions(tables: Vec<TableDefinition<'a>>) -> Result<Self, TableError> {
+        let mut table_map = HashMap::new();
+
+        for table in tables {
+            if table_map.contains_key(table.id) {
+                return Err(TableError::InvalidDefinition(format!(
+                    "table id \"{}\" is defined more than once across the loaded files",
+                    table.id
+                )));
+            }
+
+            table_map.insert(table.id, table);
+        }
+
+        let tabol = Self {
+            table_map,
+            sources: Vec::new(),
+        };
+
+        tabol.validate_tables()
+    }
+
+    /// Parses `.tbl` source text into the owned, serde-friendly model
+    /// (`OwnedTabol`) instead of a borrowed `Tabol`, so the result can be
+    /// serialized to JSON/TOML or kept around independent of `source`'s
+    /// lifetime.
+    pub fn parse(source: &str) -> Result<OwnedTabol, TableError> {
+        OwnedTabol::parse(source)
+    }
+
+    /// Builds a `Tabol` that reads its table and rule text out of `owned`,
+    /// for sessions loaded from JSON/TOML rather than the `.tbl` DSL.
+    pub fn from_owned(owned: &OwnedTabol) -> Result<Tabol<'_>, TableError> {
+        owned.to_tabol()
+    }
+
+    /// Statically proves every table can terminate, instead of actually
+    /// generating from each rule (which would itself recurse forever on a
+    /// non-terminating table). Catches two kinds of invalid definitions:
+    /// an interpolation of a table id that was never defined, and a table
+    /// whose every rule recurses into other tables with no base case.
     fn validate_tables(self) -> Result<Self, TableError> {
+        self.check_undefined_references()?;
+        self.check_productive()?;
+        self.warn_unreachable_rules();
+
+        Ok(self)
+    }
+
+    fn check_undefined_references(&self) -> Result<(), TableError> {
+        for table in self.table_map.values() {
+            for rule in table.rules.iter() {
+                for referenced_id in rule.referenced_table_ids() {
+                    if self.table_map.contains_key(referenced_id) {
+                        continue;
+                    }
+
+                    let msg = format!("no table is defined with id \"{}\"", referenced_id);
+
+                    return Err(TableError::InvalidDefinition(
+                        match self.source_containing(referenced_id) {
+                            Some(source) => describe_location(source, referenced_id, &msg),
+                            None => msg,
+                        },
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fixpoint over the table graph: a table is "productive" once it has at
+    /// least one rule composed solely of `Literal`/`DiceRoll`/`Reference`
+    /// parts or interpolations (plain or assigned) of already-productive
+    /// tables. Anything still
+    /// unproductive once the fixpoint settles can only ever recurse.
+    fn check_productive(&self) -> Result<(), TableError> {
+        let mut productive: HashSet<&str> = HashSet::new();
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (table_id, table) in self.table_map.iter() {
+                if productive.contains(table_id) {
+                    continue;
+                }
+
+                let is_productive = table.rules.iter().any(|rule| {
+                    rule.parts.iter().all(|part| match part {
+                        RuleInst::Literal(_) | RuleInst::DiceRoll(_, _) | RuleInst::Reference(_, _) => {
+                            true
+                        }
+                        RuleInst::Interpolation(id, _) | RuleInst::Assignment(_, id, _) => {
+                            productive.contains(id)
+                        }
+                    })
+                });
+
+                if is_productive {
+                    productive.insert(table_id);
+                    changed = true;
+                }
+            }
+        }
+
+        for table_id in self.table_map.keys() {
+            if !productive.contains(table_id) {
+                return Err(TableError::InvalidDefinition(format!(
+                    "table \"{}\" can never terminate: every rule recurses into other tables with no base case",
+                    table_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn warn_unreachable_rules(&self) {
         for (table_id, table) in self.table_map.iter() {
             for rule in table.rules.iter() {
-                if let Err(err) = rule.resolve(&self) {
-                    return Err(TableError::InvalidDefinition(format!(
-                        "in table \"{}\" for rule \"{}\". Original error: \"{}\"",
-                        table_id, rule.raw, err
-                    )));
+                if rule.weight == 0.0 {
+                    warn!(
+                        "rule \"{}\" in table \"{}\" has a weight of 0.0 and can never be selected",
+                        rule.raw, table_id
+                    );
                 }
             }
         }
+    }
 
-        Ok(self)
+    fn source_containing(&self, location: &str) -> Option<&'a str> {
+        let location_ptr = location.as_ptr() as usize;
+
+        self.sources.iter().copied().find(|source| {
+            let start = source.as_ptr() as usize;
+            (start..start + source.len()).contains(&location_ptr)
+        })
     }
 
     pub fn table_ids(&self) -> Vec<&str> {
         self.table_map.keys().copied().collect()
     }
 
+    pub fn contains_table(&self, id: &str) -> bool {
+        self.table_map.contains_key(id)
+    }
+
     pub fn gen(&self, id: &str) -> Result<String, TableError> {
         self.table_map
             .get(id)
@@ -153,6 +466,15 @@ impl<'a> Tabol<'a> {
 
         Ok(results)
     }
+
+    /// Like `gen_many`, but returns the results serialized as a JSON array
+    /// instead of a `Vec<String>`, for callers that want structured output.
+    pub fn gen_many_json(&self, id: &str, count: u8) -> Result<String, TableError> {
+        let results = self.gen_many(id, count)?;
+
+        serde_json::to_string(&results)
+            .map_err(|e| TableError::CallError(format!("failed to serialize results: {}", e)))
+    }
 }
 
 #[derive(Debug)]
@@ -165,16 +487,42 @@ pub struct TableDefinition<'a> {
 }
 
 impl<'a> TableDefinition<'a> {
-    pub fn new(title: &'a str, id: &'a str, rules: Vec<Rule<'a>>) -> Self {
+    /// Fails if `rules`' weights sum to `<= 0`, or if any weight is
+    /// negative or non-finite — `WeightedIndex` has no rule it could ever
+    /// select in either case, and `warn_unreachable_rules` only warns about
+    /// individual zero-weight rules, not a table where every rule is
+    /// unreachable.
+    pub fn new(title: &'a str, id: &'a str, rules: Vec<Rule<'a>>) -> Result<Self, TableError> {
         let weights: Vec<f32> = rules.iter().map(|rule| rule.weight).collect();
 
-        Self {
+        // `WeightedIndex` accepts an infinite weight and then panics on the
+        // infinite total, so reject it here along with the other invalid weights
+        if weights.iter().any(|weight| weight.is_infinite()) {
+            return Err(TableError::InvalidDefinition(format!(
+                "table \"{}\" has a rule with a negative, infinite, or NaN weight",
+                id
+            )));
+        }
+
+        let distribution = WeightedIndex::new(&weights).map_err(|e| {
+            let reason = match e {
+                WeightedError::AllWeightsZero => "has no rule with a positive weight".to_string(),
+                WeightedError::InvalidWeight => {
+                    "has a rule with a negative, infinite, or NaN weight".to_string()
+                }
+                _ => format!("has an unusable weight distribution ({})", e),
+            };
+
+            TableError::InvalidDefinition(format!("table \"{}\" {}", id, reason))
+        })?;
+
+        Ok(Self {
             title,
             id,
             rules,
-            weights: weights.to_owned(),
-            distribution: WeightedIndex::new(&weights).unwrap(),
-        }
+            weights,
+            distribution,
+        })
     }
 
     pub fn gen(&self, tables: &'a Tabol) -> Result<String, TableError> {
@@ -194,15 +542,18 @@ pub struct Rule<'a> {
 
 impl<'a> Rule<'a> {
     pub fn resolve(&self, tables: &'a Tabol) -> Result<String, TableError> {
-        // keep track of context
-        // forward pass to resolve all interpolations
-        // backwards pass to resolve built-ins (e.g. article)
-        let resolved: Result<Vec<String>, TableError> = self
-            .parts
-            .iter()
-            .map(|part| match part {
-                RuleInst::DiceRoll(count, sides) => Ok(roll_dice(*count, *sides).to_string()),
-                RuleInst::Literal(str) => Ok(str.to_string()),
+        // keep track of context so `Assignment`/`Reference` pairs can thread a
+        // value through the rest of the rule; built left-to-right alongside
+        // the forward pass, since references may only follow their binding
+        let mut context: HashMap<&str, String> = HashMap::new();
+        let mut output = String::new();
+
+        for part in self.parts.iter() {
+            match part {
+                RuleInst::DiceRoll(count, sides) => {
+                    output.push_str(&roll_dice(*count, *sides).to_string());
+                }
+                RuleInst::Literal(str) => output.push_str(str),
                 RuleInst::Interpolation(id, opts) => {
                     let mut resolved = tables.gen(id)?;
 
@@ -210,12 +561,49 @@ impl<'a> Rule<'a> {
                         opt.apply(&mut resolved);
                     }
 
-                    Ok(resolved)
+                    output.push_str(&resolved);
+                }
+                RuleInst::Assignment(name, id, opts) => {
+                    let mut resolved = tables.gen(id)?;
+
+                    for opt in opts {
+                        opt.apply(&mut resolved);
+                    }
+
+                    output.push_str(&resolved);
+                    context.insert(name, resolved);
+                }
+                RuleInst::Reference(name, opts) => {
+                    let mut resolved = context
+                        .get(name)
+                        .ok_or_else(|| {
+                            TableError::CallError(format!(
+                                "Variable \"{}\" is referenced before it is assigned",
+                                name
+                            ))
+                        })?
+                        .clone();
+
+                    for opt in opts {
+                        opt.apply(&mut resolved);
+                    }
+
+                    output.push_str(&resolved);
                 }
-            })
-            .collect();
+            }
+        }
+
+        Ok(output)
+    }
 
-        Ok(resolved?.join(""))
+    /// The table ids this rule calls into, for the static well-formedness
+    /// checks in `Tabol::validate_tables`. `Reference`s don't count, since
+    /// they read from the rule's own context rather than another table.
+    fn referenced_table_ids(&self) -> impl Iterator<Item = TableId<'a>> + '_ {
+        self.parts.iter().filter_map(|part| match part {
+            RuleInst::Interpolation(id, _) | RuleInst::Assignment(_, id, _) => Some(*id),
+            _ => None,
+        })
     }
 }
 
@@ -223,42 +611,146 @@ impl<'a> Rule<'a> {
 pub enum RuleInst<'a> {
     DiceRoll(usize, usize), // (count, sides)
     Literal(&'a str),
-    Interpolation(TableId<'a>, Vec<FilterOp>),
+    Interpolation(TableId<'a>, Vec<FilterOp<'a>>),
+    /// `{{name = table|filters}}` — generate from `table`, apply `filters`, and bind
+    /// the result to `name` so a later `Reference` in the same rule can reuse it.
+    Assignment(&'a str, TableId<'a>, Vec<FilterOp<'a>>),
+    /// `{{$name|filters}}` — emit the value bound by an earlier `Assignment` in
+    /// this rule, re-applying `filters` to the looked-up value.
+    Reference(&'a str, Vec<FilterOp<'a>>),
 }
 
+/// One step of an interpolation's `|`-chained filter pipeline, e.g. the
+/// `truncate:12` in `{{name|truncate:12}}`. `name` is looked up in the
+/// filter registry at apply time; `args` are the raw `:`-separated tokens.
 #[derive(Debug, Clone)]
-pub enum FilterOp {
-    DefiniteArticle,
-    IndefiniteArticle,
-    Capitalize,
+pub struct FilterOp<'a> {
+    pub name: &'a str,
+    pub args: Vec<&'a str>,
 }
 
-impl FilterOp {
+impl<'a> FilterOp<'a> {
     pub fn apply(&self, value: &mut String) {
-        match self {
-            FilterOp::DefiniteArticle => {
-                value.insert_str(0, "the ");
-            }
-            FilterOp::IndefiniteArticle
-                if value.starts_with('a')
-                    || value.starts_with('e')
-                    || value.starts_with('i')
-                    || value.starts_with('o')
-                    || value.starts_with('u') =>
-            {
-                value.insert_str(0, "an ");
-            }
-            FilterOp::IndefiniteArticle => {
-                value.insert_str(0, "a ");
-            }
-            FilterOp::Capitalize => {
-                let mut chars = value.chars();
-                if let Some(first) = chars.next() {
-                    *value = format!("{}{}", first.to_uppercase(), chars.as_str());
-                }
-            }
+        let registry = filter_registry().lock().unwrap();
+
+        if let Some(handler) = registry.get(self.name) {
+            handler(value, &self.args);
+        }
+    }
+}
+
+/// A filter handler: mutates `value` in place given its `:`-separated
+/// argument tokens (empty if the filter took none).
+pub type FilterHandler = fn(&mut String, &[&str]);
+
+static FILTER_REGISTRY: OnceLock<Mutex<HashMap<&'static str, FilterHandler>>> = OnceLock::new();
+
+fn filter_registry() -> &'static Mutex<HashMap<&'static str, FilterHandler>> {
+    FILTER_REGISTRY.get_or_init(|| {
+        let mut registry: HashMap<&'static str, FilterHandler> = HashMap::new();
+
+        registry.insert("definite", filter_definite_article);
+        registry.insert("indefinite", filter_indefinite_article);
+        registry.insert("capitalize", filter_capitalize);
+        registry.insert("pluralize", filter_pluralize);
+        registry.insert("repeat", filter_repeat);
+        registry.insert("upper", filter_upper);
+        registry.insert("truncate", filter_truncate);
+        registry.insert("replace", filter_replace);
+
+        Mutex::new(registry)
+    })
+}
+
+/// Installs (or overrides) a filter usable as `|name` or `|name:arg1:arg2`
+/// in a rule's interpolation pipeline. Must be called before the table
+/// definitions that use it are parsed (i.e. before `Tabol::new` /
+/// `Tabol::from_sources`), since an unregistered filter name is rejected
+/// at parse time.
+pub fn register_filter(name: &'static str, handler: FilterHandler) {
+    filter_registry().lock().unwrap().insert(name, handler);
+}
+
+pub(crate) fn is_registered_filter(name: &str) -> bool {
+    filter_registry().lock().unwrap().contains_key(name)
+}
+
+fn filter_definite_article(value: &mut String, _args: &[&str]) {
+    value.insert_str(0, "the ");
+}
+
+fn filter_indefinite_article(value: &mut String, _args: &[&str]) {
+    if value.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        value.insert_str(0, "an ");
+    } else {
+        value.insert_str(0, "a ");
+    }
+}
+
+fn filter_capitalize(value: &mut String, _args: &[&str]) {
+    let mut chars = value.chars();
+    if let Some(first) = chars.next() {
+        *value = format!("{}{}", first.to_uppercase(), chars.as_str());
+    }
+}
+
+fn filter_pluralize(value: &mut String, _args: &[&str]) {
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u');
+
+    if value.ends_with(['s', 'x', 'z']) || value.ends_with("ch") || value.ends_with("sh") {
+        value.push_str("es");
+        return;
+    }
+
+    if value.ends_with('y') {
+        let consonant_y = value[..value.len() - 1]
+            .chars()
+            .last()
+            .map(|c| !is_vowel(c))
+            .unwrap_or(false);
+
+        if consonant_y {
+            value.truncate(value.len() - 1);
+            value.push_str("ies");
+            return;
         }
     }
+
+    value.push('s');
+}
+
+fn filter_repeat(value: &mut String, args: &[&str]) {
+    let count: usize = args.first().and_then(|s| s.parse().ok()).unwrap_or(1);
+    let once = value.clone();
+
+    value.clear();
+    for _ in 0..count {
+        value.push_str(&once);
+    }
+}
+
+fn filter_upper(value: &mut String, _args: &[&str]) {
+    *value = value.to_uppercase();
+}
+
+fn filter_truncate(value: &mut String, args: &[&str]) {
+    let Some(max_chars) = args.first().and_then(|s| s.parse::<usize>().ok()) else {
+        return;
+    };
+
+    let byte_len = value
+        .char_indices()
+        .nth(max_chars)
+        .map(|(idx, _)| idx)
+        .unwrap_or(value.len());
+
+    value.truncate(byte_len);
+}
+
+fn filter_replace(value: &mut String, args: &[&str]) {
+    if let (Some(from), Some(to)) = (args.first(), args.get(1)) {
+        *value = value.replace(*from, to);
+    }
 }
 
 pub fn roll_dice(count: usize, sides: usize) -> usize {
@@ -290,4 +782,286 @@ mod tests {
             assert!(roll <= 50);
         }
     }
+
+    fn leak(source: String) -> &'static str {
+        Box::leak(source.into_boxed_str())
+    }
+
+    #[test]
+    fn test_variable_binding_reuse() {
+        let source = leak(
+            "\
+---
+id: npc
+title: NPC
+---
+1: {{name = npc_name}} draws their sword. {{$name}} attacks!
+
+---
+id: npc_name
+title: NPC Name
+---
+1: Aragorn
+"
+            .to_string(),
+        );
+
+        let tabol = Tabol::new(source).unwrap();
+
+        assert_eq!(
+            tabol.gen("npc").unwrap(),
+            "Aragorn draws their sword. Aragorn attacks!"
+        );
+    }
+
+    #[test]
+    fn test_variable_reference_before_assignment_errors() {
+        let source = leak(
+            "\
+---
+id: broken
+title: Broken
+---
+1: {{$name}} appears!
+"
+            .to_string(),
+        );
+
+        let tabol = Tabol::new(source).unwrap();
+
+        assert!(matches!(tabol.gen("broken"), Err(TableError::CallError(_))));
+    }
+
+    #[test]
+    fn test_validate_tables_rejects_non_terminating_table() {
+        let source = leak(
+            "\
+---
+id: loop
+title: Loop
+---
+1: {{loop}}
+"
+            .to_string(),
+        );
+
+        assert!(matches!(
+            Tabol::new(source),
+            Err(TableError::InvalidDefinition(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_tables_rejects_undefined_reference() {
+        let source = leak(
+            "\
+---
+id: npc
+title: NPC
+---
+1: {{missing_table}}
+"
+            .to_string(),
+        );
+
+        assert!(matches!(
+            Tabol::new(source),
+            Err(TableError::InvalidDefinition(_))
+        ));
+    }
+
+    #[test]
+    fn test_zero_weight_table_is_an_error_not_a_panic() {
+        let source = leak(
+            "\
+---
+id: t
+title: T
+---
+0: foo
+"
+            .to_string(),
+        );
+
+        assert!(matches!(
+            Tabol::new(source),
+            Err(TableError::InvalidDefinition(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_sources_merges_tables_defined_across_multiple_files() {
+        let npc = leak(
+            "\
+---
+id: npc
+title: NPC
+---
+1: {{npc_name}}
+"
+            .to_string(),
+        );
+        let npc_name = leak(
+            "\
+---
+id: npc_name
+title: NPC Name
+---
+1: Aragorn
+"
+            .to_string(),
+        );
+
+        let tabol = Tabol::from_sources(vec![npc, npc_name]).unwrap();
+
+        assert_eq!(tabol.gen("npc").unwrap(), "Aragorn");
+        assert_eq!(tabol.gen("npc_name").unwrap(), "Aragorn");
+    }
+
+    #[test]
+    fn test_from_sources_rejects_table_id_defined_in_more_than_one_file() {
+        let a = leak(
+            "\
+---
+id: npc_name
+title: NPC Name
+---
+1: Aragorn
+"
+            .to_string(),
+        );
+        let b = leak(
+            "\
+---
+id: npc_name
+title: NPC Name (again)
+---
+1: Legolas
+"
+            .to_string(),
+        );
+
+        assert!(matches!(
+            Tabol::from_sources(vec![a, b]),
+            Err(TableError::InvalidDefinition(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_import_graph_resolves_imports_relative_to_importing_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "tabol_test_resolve_import_graph_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let entry_path = dir.join("npc.tbl");
+        std::fs::write(
+            &entry_path,
+            "\
+---
+id: npc
+title: NPC
+import: npc_name
+---
+1: {{npc_name}}
+",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("npc_name.tbl"),
+            "\
+---
+id: npc_name
+title: NPC Name
+---
+1: Aragorn
+",
+        )
+        .unwrap();
+
+        let sources = Tabol::resolve_import_graph(&entry_path).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sources.len(), 2);
+        assert!(sources.iter().any(|s| s.contains("id: npc_name")));
+    }
+
+    #[test]
+    fn test_filter_pluralize() {
+        let cases = [
+            ("fox", "foxes"),
+            ("church", "churches"),
+            ("city", "cities"),
+            ("day", "days"),
+            ("sword", "swords"),
+        ];
+
+        for (input, expected) in cases {
+            let mut value = input.to_string();
+            filter_pluralize(&mut value, &[]);
+            assert_eq!(value, expected);
+        }
+    }
+
+    #[test]
+    fn test_filter_truncate() {
+        let mut value = "hello world".to_string();
+        filter_truncate(&mut value, &["5"]);
+        assert_eq!(value, "hello");
+
+        // shorter than max_chars is left alone
+        let mut value = "hi".to_string();
+        filter_truncate(&mut value, &["10"]);
+        assert_eq!(value, "hi");
+
+        // missing/invalid argument is a no-op rather than a panic
+        let mut value = "hi".to_string();
+        filter_truncate(&mut value, &[]);
+        assert_eq!(value, "hi");
+    }
+
+    #[test]
+    fn test_filter_repeat() {
+        let mut value = "ab".to_string();
+        filter_repeat(&mut value, &["3"]);
+        assert_eq!(value, "ababab");
+
+        // missing argument defaults to a single repetition
+        let mut value = "ab".to_string();
+        filter_repeat(&mut value, &[]);
+        assert_eq!(value, "ab");
+    }
+
+    #[test]
+    fn test_filter_replace() {
+        let mut value = "the old sword".to_string();
+        filter_replace(&mut value, &["old", "new"]);
+        assert_eq!(value, "the new sword");
+
+        // missing `to` argument is a no-op rather than a panic
+        let mut value = "the old sword".to_string();
+        filter_replace(&mut value, &["old"]);
+        assert_eq!(value, "the old sword");
+    }
+
+    #[test]
+    fn test_unknown_filter_is_a_parse_error_not_a_panic() {
+        let source = leak(
+            "\
+---
+id: npc
+title: NPC
+---
+1: {{npc|not_a_real_filter}}
+"
+            .to_string(),
+        );
+
+        assert!(matches!(
+            Tabol::new(source),
+            Err(TableError::ParseError(_, _))
+        ));
+    }
 }